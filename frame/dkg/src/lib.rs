@@ -17,31 +17,43 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use frame_support::{debug, decl_module, decl_storage, Parameter};
+use codec::{Decode, Encode};
+use frame_support::{
+	debug, decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult, ensure,
+	traits::Get, Parameter,
+};
 use frame_system::{
 	ensure_signed,
 	offchain::{AppCrypto, CreateSignedTransaction, SendSignedTransaction, Signer},
 };
-use sp_runtime::{offchain::storage::StorageValueRef, traits::Member, RuntimeAppPublic};
+use sp_runtime::{offchain::storage::StorageValueRef, traits::Member, RuntimeAppPublic, RuntimeDebug};
 use sp_std::{convert::TryInto, vec::Vec};
 
-use sp_dkg::{Commitment, EncryptionPublicKey, Scalar};
-
-// TODO maybe we could control the round boundaries with events?
-// These should be perhaps in some config in the genesis block?
-pub const END_ROUND_0: u32 = 5;
-pub const END_ROUND_1: u32 = 10;
-pub const END_ROUND_2: u32 = 15;
+use sp_dkg::{
+	Commitment, DecryptionProof, DleqProof, EncryptionKey, EncryptionPublicKey, Scalar,
+	SchnorrProof, Signature,
+};
 
 // n is the number of nodes in the committee
 // node indices are 1-based: 1, 2, ..., n
 // t is the threshold: it is necessary and sufficient to have t shares to combine
 // the degree of the polynomial is thus t-1
 
-// Should be a decrypted share (milestone 2) + along with a proof of descryption (only in milestone 3)
-// #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
-// pub struct DisputeAgainstDealer {
-// }
+/// A complaint filed by `complainant` against `dealer`: the share `dealer` sent it
+/// failed the Feldman commitment check. `share` is the decrypted value the complainant
+/// received, `shared_key` is the ECDH key it claims to have decrypted it with, and
+/// `dleq_proof` proves `shared_key` was honestly derived, i.e. that
+/// `log_g(EncryptionPKs[complainant]) == log_{EncryptionPKs[dealer]}(shared_key)`.
+/// Without that proof a complainant could decrypt its share correctly and then simply
+/// lie about `share` to get an honest dealer marked as faulty.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct DisputeAgainstDealer {
+	pub dealer: AuthIndex,
+	pub complainant: AuthIndex,
+	pub share: Scalar,
+	pub shared_key: EncryptionKey,
+	pub dleq_proof: DleqProof,
+}
 
 // TODO the following and the definition of AuthorityId probably needs a refactor. The problem is
 // that the trait CreateSignedTransaction needed by Signer imposes that AuthorityId must extend
@@ -131,14 +143,83 @@ pub trait Trait: CreateSignedTransaction<Call<Self>> {
 
 	/// The overarching dispatch call type.
 	type Call: From<Call<Self>>;
+
+	/// The overarching event type.
+	type Event: From<Event> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The block, relative to genesis, at which round 0 (broadcasting encryption keys)
+	/// ends and round 1 begins.
+	type EndRound0: Get<Self::BlockNumber>;
+	/// The block at which round 1 (dealing secret shares) ends and round 2 begins.
+	type EndRound1: Get<Self::BlockNumber>;
+	/// The block at which round 2 (disputing bad shares) ends and round 3 begins.
+	type EndRound2: Get<Self::BlockNumber>;
+	/// The block at which round 3 (finalizing the master key) ends.
+	type EndRound3: Get<Self::BlockNumber>;
+
+	/// When `true`, the initial key generation runs as a single-message SimplPedPoP-style
+	/// round instead of the sequential round 0 - round 3 protocol: dealers bundle their
+	/// commitments, encrypted shares and a proof of possession into one `deal_simple_pedpop`
+	/// transaction before `EndRound0`, and recipients' encryption keys come from genesis
+	/// (`config(encryption_pks)`) rather than a round 0 broadcast.
+	type SimplPedPoP: Get<bool>;
 }
 
 // An index of the authority on the list of validators.
 pub type AuthIndex = u64;
 
+decl_event!(
+	pub enum Event {
+		/// The given DKG round started.
+		RoundStarted(u32),
+		/// The given DKG round ended.
+		RoundEnded(u32),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The configured threshold must satisfy `0 < threshold <= n_members`.
+		InvalidThreshold,
+		/// A dealer's `comm_poly` must carry exactly `Threshold` commitments, one per
+		/// coefficient of its degree-`(Threshold - 1)` polynomial.
+		InvalidCommitmentLength,
+		/// This dealer already posted its commitments/shares for the current round.
+		DuplicatedDealer,
+		/// A Feldman commitment equal to the group identity never arises from an
+		/// honestly sampled polynomial coefficient, so it is rejected outright.
+		CommitmentIsIdentity,
+		/// The submitted round-boundary hash does not match the chain's.
+		WrongRoundHash,
+		/// `ix` is not a valid index into `Authorities`, or is not the index of the
+		/// account that signed the extrinsic.
+		UnknownAuthorityIndex,
+		/// `deal_simple_pedpop` was submitted while `Trait::SimplPedPoP` is `false`.
+		SimplPedPoPDisabled,
+		/// The dealer's proof of possession of `comm_poly[0]`'s discrete log did not verify.
+		InvalidProofOfPossession,
+		/// `ciphertext_id` does not refer to a ciphertext submitted via `submit_ciphertext`.
+		UnknownCiphertext,
+		/// `ciphertext_id` already has a ciphertext registered against it.
+		DuplicatedCiphertext,
+		/// The submitted decryption share's DLEQ proof against the member's verification
+		/// key did not verify.
+		InvalidDecryptionShare,
+		/// `finalize_round3` was called before the dealing/dispute phase it finalizes has
+		/// closed -- `CurrentRound` has not yet reached 3 (standard flow), or the dealing
+		/// window (`EndRound0`) is still open (SimplPedPoP).
+		Round2NotFinished,
+	}
+}
+
 decl_storage! {
 	trait Store for Module<T: Trait> as DKGWorker {
 
+		/// Which round the protocol is currently in (0..=3, then 4 once round 3 has
+		/// ended), advanced and evented by `on_initialize` as the chain crosses each
+		/// `Trait::EndRoundN`. Starts at 0.
+		CurrentRound get(fn current_round): u32;
+
 		// round 0
 
 		// EncryptionPKs: Vec<Option<EncryptionPubKey>>;
@@ -163,70 +244,516 @@ decl_storage! {
 		// 2) there was no succesful dispute that proves cheating of (i+1)th node in round 2
 		IsCorrectDealer: Vec<bool>;
 
+		// upheld disputes against a dealer, keyed by the dealer's index -- each one
+		// also set IsCorrectDealer for that dealer to false.
+		Disputes: map hasher(twox_64_concat) AuthIndex => Vec<DisputeAgainstDealer>;
+
+		// round 3
+
+		/// The group's BLS public key: `Σ CommittedPolynomials[dealer][0]` over the
+		/// dealers `IsCorrectDealer` marks honest. `None` until `finalize_round3` sets it.
+		/// Doubles as the group's ElGamal encryption public key -- `Ciphertexts` are
+		/// encrypted under this same point, with `submit_decryption_share` providing the
+		/// matching threshold decryption.
+		MasterPublicKey get(fn master_public_key): Option<Commitment>;
+
+		/// Partial signatures submitted so far for a message, keyed by the message
+		/// itself -- once `Threshold` distinct shares are in they are combined and
+		/// verified, and the result is stored in `CombinedSignatures`.
+		PartialSignatures get(fn partial_signatures):
+			map hasher(blake2_128_concat) Vec<u8> => Vec<(AuthIndex, Signature)>;
+
+		/// The combined group signature for a message, once enough partial signatures
+		/// were submitted and the result verified against `MasterPublicKey`.
+		CombinedSignatures get(fn combined_signatures):
+			map hasher(blake2_128_concat) Vec<u8> => Signature;
+
+		/// ElGamal ciphertexts `(c1, c2)` registered for threshold decryption, keyed by
+		/// an application-chosen id -- `c2` is the plaintext group element blinded by
+		/// `c1^sk` for the secret key behind `MasterPublicKey`.
+		Ciphertexts get(fn ciphertexts): map hasher(blake2_128_concat) Vec<u8> => (Commitment, Commitment);
+
+		/// Verified decryption shares submitted so far for a ciphertext, keyed by its id
+		/// -- once `Threshold` distinct shares are in they are combined and the result
+		/// is stored in `Decryptions`.
+		DecryptionShares get(fn decryption_shares):
+			map hasher(blake2_128_concat) Vec<u8> => Vec<(AuthIndex, Commitment)>;
+
+		/// The recovered plaintext group element for a ciphertext, once enough
+		/// decryption shares were submitted and combined.
+		Decryptions get(fn decryptions): map hasher(blake2_128_concat) Vec<u8> => Commitment;
+
+		// resharing
+
+		/// The epoch the committee most recently finished resharing into; 0 until the
+		/// first resharing completes.
+		CurrentEpoch get(fn current_epoch): u32;
+
+		/// The committee being reshared into, i.e. resharing is in progress iff this is
+		/// non-empty. Cleared once `finalize_resharing` succeeds.
+		NextAuthorities get(fn next_authorities): Vec<T::AuthorityId>;
+
+		/// A `NextAuthorities` member's resharing-round encryption key, keyed by the
+		/// target epoch and the member's index in `NextAuthorities`. Reuses whatever
+		/// ECDH key the member already holds rather than generating a fresh one.
+		ResharingEncryptionPKs get(fn resharing_encryption_pks):
+			double_map hasher(twox_64_concat) u32, hasher(twox_64_concat) AuthIndex => EncryptionPublicKey;
+
+		/// An old shareholder's fresh Feldman commitments for the target epoch, whose
+		/// constant term is that shareholder's own final secret-key share -- keyed by
+		/// epoch and the shareholder's index in the *old* `Authorities`.
+		ResharingPolynomials get(fn resharing_polynomials):
+			double_map hasher(twox_64_concat) u32, hasher(twox_64_concat) AuthIndex => Vec<Commitment>;
+
+		/// The matching per-`NextAuthorities`-recipient encrypted shares for
+		/// `ResharingPolynomials`.
+		ResharingShares get(fn resharing_shares):
+			double_map hasher(twox_64_concat) u32, hasher(twox_64_concat) AuthIndex => Vec<Vec<u8>>;
+
 		/// The current authorities
 		pub Authorities get(fn authorities): Vec<T::AuthorityId>;
 
+		/// The `AccountId` that signs on behalf of each `Authorities` entry, in the same
+		/// order -- lets dispatchables bind a claimed `ix` to whichever account actually
+		/// submitted the extrinsic (`ensure_own_authority_index`) without having to
+		/// convert between the unrelated `AccountId`/`AuthorityId` key types.
+		pub AuthorityAccounts get(fn authority_accounts): Vec<T::AccountId>;
+
 		/// The threshold of BLS scheme
 		pub Threshold: u32;
 	}
 	add_extra_genesis {
 		config(authorities): Vec<T::AuthorityId>;
+		/// One `AccountId` per `authorities` entry, in the same (post-sort, see
+		/// `initialize_authorities`) order -- the account that is expected to submit
+		/// that authority's extrinsics.
+		config(authority_accounts): Vec<T::AccountId>;
 		config(threshold): u32;
+		/// One `EncryptionPublicKey` per `authorities` entry, in the same order --
+		/// only used in `Trait::SimplPedPoP` mode, where dealers need every
+		/// recipient's encryption key up front and so cannot wait for a round 0
+		/// broadcast. Leave empty outside that mode.
+		config(encryption_pks): Vec<EncryptionPublicKey>;
 		build(|config| {
 			Module::<T>::initialize_authorities(&config.authorities);
-			Module::<T>::set_threshold(config.threshold);
+			Module::<T>::initialize_authority_accounts(&config.authority_accounts)
+				.expect("DKG: invalid authority_accounts in genesis config");
+			Module::<T>::set_threshold(config.threshold)
+				.expect("DKG: invalid threshold in genesis config");
+			Module::<T>::initialize_encryption_pks(&config.encryption_pks)
+				.expect("DKG: invalid encryption_pks in genesis config");
 		})
 	}
 }
 
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
 
 		// TODO: we need to be careful with weights -- for now they are 0, but need to think about them later
 		#[weight = 0]
-		pub fn post_encryption_key(origin, pk: EncryptionPublicKey, ix: AuthIndex)  {
+		pub fn post_encryption_key(origin, pk: EncryptionPublicKey, ix: AuthIndex) -> DispatchResult {
 			let now = <frame_system::Module<T>>::block_number();
-			let _ = ensure_signed(origin)?;
+			let who = ensure_signed(origin)?;
 			debug::RuntimeLogger::init();
 			debug::info!("DKG POST_ENCRYPTION_KEY CALL: BLOCK_NUMBER: {:?} WHO {:?}", now, ix);
-			// TODO should we block receiving pk after END_ROUND_0?
+			ensure!(now < T::EndRound0::get(), "DKG: round 0 has already closed");
+			Self::ensure_own_authority_index(who, ix)?;
 			EncryptionPKs::insert(ix, pk);
+			Ok(())
 		}
 
 		#[weight = 0]
-		pub fn post_secret_shares(origin, shares: Vec<Vec<u8>>, comm_poly: Vec<Commitment>, ix: AuthIndex, hash_round0: T::Hash) {
+		pub fn post_secret_shares(origin, shares: Vec<Vec<u8>>, comm_poly: Vec<Commitment>, ix: AuthIndex, hash_round0: T::Hash) -> DispatchResult {
 			let now = <frame_system::Module<T>>::block_number();
 			debug::RuntimeLogger::init();
 			debug::info!("DKG POST_SECRET_SHARES CALL: BLOCK_NUMBER: {:?} WHO {:?}", now, ix);
+			let who = ensure_signed(origin)?;
+			ensure!(now < T::EndRound1::get(), "DKG: round 1 has already closed");
+			Self::ensure_own_authority_index(who, ix)?;
+			ensure!(!CommittedPolynomials::contains_key(ix), Error::<T>::DuplicatedDealer);
+			ensure!(comm_poly.len() == Threshold::get() as usize, Error::<T>::InvalidCommitmentLength);
+			ensure!(
+				comm_poly.iter().all(|c| *c != Commitment::identity()),
+				Error::<T>::CommitmentIsIdentity,
+			);
+			let correct_hash_round0 = <frame_system::Module<T>>::block_hash(T::EndRound0::get());
+			ensure!(hash_round0 == correct_hash_round0, Error::<T>::WrongRoundHash);
+			EncryptedSharesLists::insert(ix, shares);
+			CommittedPolynomials::insert(ix, comm_poly);
+			Ok(())
+		}
+
+		#[weight = 0]
+		pub fn round2(origin, disputes: Vec<DisputeAgainstDealer>, _hash_round1: T::Hash) -> DispatchResult {
+			let now = <frame_system::Module<T>>::block_number();
+			let who = ensure_signed(origin)?;
+			debug::RuntimeLogger::init();
+
+			ensure!(now < T::EndRound2::get(), "DKG: round 2 has already closed");
+
+			let n_members = <Authorities<T>>::get().len() as AuthIndex;
+			ensure!(
+				disputes.iter().all(|d| d.dealer < n_members && d.complainant < n_members),
+				Error::<T>::UnknownAuthorityIndex,
+			);
+			for dispute in &disputes {
+				Self::ensure_own_authority_index(who.clone(), dispute.complainant)?;
+			}
+
+			Self::ensure_is_correct_dealer_initialized();
+
+			for dispute in disputes {
+				if Self::check_dispute(&dispute) {
+					debug::info!(
+						"DKG ROUND2 CALL: upheld dispute against dealer {:?} filed by {:?}",
+						dispute.dealer,
+						dispute.complainant,
+					);
+					IsCorrectDealer::mutate(|correct| {
+						if let Some(is_correct) = correct.get_mut(dispute.dealer as usize) {
+							*is_correct = false;
+						}
+					});
+					Disputes::mutate(dispute.dealer, |filed| filed.push(dispute));
+				} else {
+					debug::info!(
+						"DKG ROUND2 CALL: rejecting invalid dispute against dealer {:?} filed by {:?}",
+						dispute.dealer,
+						dispute.complainant,
+					);
+				}
+			}
+
+			Ok(())
+		}
+
+		/// The SimplPedPoP-mode counterpart of `post_encryption_key` + `post_secret_shares`:
+		/// a dealer bundles its commitments, every recipient's encrypted share and a
+		/// Schnorr proof of possession of `comm_poly[0]`'s discrete log into one
+		/// transaction. The proof stands in for the round 2 dispute period as far as
+		/// rogue-key attacks on the aggregated `MasterPublicKey` go, so a dealer is
+		/// trusted as soon as it verifies.
+		#[weight = 0]
+		pub fn deal_simple_pedpop(
+			origin,
+			comm_poly: Vec<Commitment>,
+			shares: Vec<Vec<u8>>,
+			ix: AuthIndex,
+			pop: SchnorrProof,
+		) -> DispatchResult {
+			let now = <frame_system::Module<T>>::block_number();
 			let _ = ensure_signed(origin)?;
-			let round0_number: T::BlockNumber = END_ROUND_0.into();
-			let correct_hash_round0 = <frame_system::Module<T>>::block_hash(round0_number);
-			if hash_round0 != correct_hash_round0 {
-				debug::info!("DKG POST_SECRET_SHARES CALL: received secret shares for wrong hash_round0:
-					{:?} instead of {:?} from {:?}",hash_round0, correct_hash_round0, ix);
+			debug::RuntimeLogger::init();
+			debug::info!("DKG DEAL_SIMPLE_PEDPOP CALL: BLOCK_NUMBER: {:?} WHO {:?}", now, ix);
+
+			ensure!(T::SimplPedPoP::get(), Error::<T>::SimplPedPoPDisabled);
+			ensure!(now < T::EndRound0::get(), "DKG: round 0 has already closed");
+			ensure!((ix as usize) < <Authorities<T>>::get().len(), Error::<T>::UnknownAuthorityIndex);
+			ensure!(!CommittedPolynomials::contains_key(ix), Error::<T>::DuplicatedDealer);
+			ensure!(comm_poly.len() == Threshold::get() as usize, Error::<T>::InvalidCommitmentLength);
+			ensure!(
+				comm_poly.iter().all(|c| *c != Commitment::identity()),
+				Error::<T>::CommitmentIsIdentity,
+			);
+
+			let session_id = <frame_system::Module<T>>::block_hash(T::BlockNumber::default());
+			let mut context = ix.encode();
+			context.extend(session_id.encode());
+			ensure!(pop.verify(&comm_poly[0], &context), Error::<T>::InvalidProofOfPossession);
+
+			EncryptedSharesLists::insert(ix, shares);
+			CommittedPolynomials::insert(ix, comm_poly);
+
+			Self::ensure_is_correct_dealer_initialized();
+			IsCorrectDealer::mutate(|correct| {
+				if let Some(is_correct) = correct.get_mut(ix as usize) {
+					*is_correct = true;
+				}
+			});
+
+			Ok(())
+		}
+
+		#[weight = 0]
+		pub fn finalize_round3(origin) -> DispatchResult {
+			let _who = ensure_signed(origin)?;
+			debug::RuntimeLogger::init();
+
+			// In the standard multi-round flow, round 2's disputes are only resolved once
+			// `CurrentRound` reaches 3 -- finalizing before then could lock in a
+			// `MasterPublicKey` that includes a dealer a later dispute would have caught.
+			// SimplPedPoP has no separate dispute phase (verification is folded into the
+			// single dealing round via the Schnorr proof of possession), but it still has a
+			// dealing window: finalizing while `deal_simple_pedpop` txs can still land would
+			// sum `MasterPublicKey` over whichever strict subset of dealers happened to be
+			// in first, permanently excluding the rest even though shares are later
+			// aggregated over the full honest set.
+			let now = <frame_system::Module<T>>::block_number();
+			ensure!(
+				if T::SimplPedPoP::get() {
+					now >= T::EndRound0::get()
+				} else {
+					Self::current_round() == 3
+				},
+				Error::<T>::Round2NotFinished,
+			);
+
+			if MasterPublicKey::get().is_none() {
+				Self::ensure_is_correct_dealer_initialized();
+				let is_correct = IsCorrectDealer::get();
+				let n_members = <Authorities<T>>::get().len() as u64;
+				let n_correct = is_correct.iter().filter(|correct| **correct).count();
+
+				if n_correct < Threshold::get() as usize {
+					debug::info!(
+						"DKG FINALIZE_ROUND3 CALL: only {:?} qualified dealers so far, not enough to finalize",
+						n_correct,
+					);
+				} else {
+					let master_key = (0..n_members)
+						.filter(|dealer| is_correct.get(*dealer as usize).copied().unwrap_or(false))
+						.map(|dealer| Self::committed_polynomilas(dealer)[0])
+						.fold(Commitment::identity(), |acc, c0| acc + c0);
+
+					debug::info!("DKG FINALIZE_ROUND3 CALL: finalized MasterPublicKey {:?}", master_key);
+					MasterPublicKey::put(master_key);
+				}
+			}
+
+			Ok(())
+		}
+
+		#[weight = 0]
+		pub fn submit_partial_signature(origin, message: Vec<u8>, sig_share: Signature, ix: AuthIndex) {
+			let _who = ensure_signed(origin)?;
+			debug::RuntimeLogger::init();
+
+			if CombinedSignatures::contains_key(&message) {
+				debug::info!("DKG SUBMIT_PARTIAL_SIGNATURE CALL: {:?} already has a combined signature", message);
+			} else {
+				PartialSignatures::mutate(&message, |shares| shares.push((ix, sig_share)));
+				Self::try_combine_signature(&message);
+			}
+		}
+
+		/// Registers an ElGamal ciphertext `(c1, c2)`, encrypted under `MasterPublicKey`,
+		/// for threshold decryption via `submit_decryption_share`.
+		#[weight = 0]
+		pub fn submit_ciphertext(origin, ciphertext_id: Vec<u8>, c1: Commitment, c2: Commitment) -> DispatchResult {
+			let _who = ensure_signed(origin)?;
+			debug::RuntimeLogger::init();
+
+			ensure!(!Ciphertexts::contains_key(&ciphertext_id), Error::<T>::DuplicatedCiphertext);
+			Ciphertexts::insert(&ciphertext_id, (c1, c2));
+			Ok(())
+		}
+
+		/// Member `ix` contributes `dec_share = c1^sk_i` towards decrypting the
+		/// ciphertext registered as `ciphertext_id`, proving via `dleq_proof` that the
+		/// same `sk_i` underlies both `dec_share` (base `c1`) and its verification key
+		/// (base `g`) -- so a combiner only ever aggregates honestly derived shares. Once
+		/// `Threshold` distinct, verified shares are in, `c1^sk` is interpolated in the
+		/// exponent (mirroring `combine_signature_shares`) and `c2 - c1^sk` is recovered
+		/// into `Decryptions`.
+		#[weight = 0]
+		pub fn submit_decryption_share(
+			origin,
+			ciphertext_id: Vec<u8>,
+			dec_share: Commitment,
+			dleq_proof: DecryptionProof,
+			ix: AuthIndex,
+		) -> DispatchResult {
+			let _who = ensure_signed(origin)?;
+			debug::RuntimeLogger::init();
+
+			ensure!((ix as usize) < <Authorities<T>>::get().len(), Error::<T>::UnknownAuthorityIndex);
+			ensure!(Ciphertexts::contains_key(&ciphertext_id), Error::<T>::UnknownCiphertext);
+
+			if Decryptions::contains_key(&ciphertext_id) {
+				debug::info!("DKG SUBMIT_DECRYPTION_SHARE CALL: {:?} is already decrypted", ciphertext_id);
+				return Ok(());
+			}
+
+			let (c1, _) = Self::ciphertexts(&ciphertext_id);
+			let vk = Self::verification_key(ix);
+			ensure!(dleq_proof.verify(&vk, &c1, &dec_share), Error::<T>::InvalidDecryptionShare);
+
+			DecryptionShares::mutate(&ciphertext_id, |shares| shares.push((ix, dec_share)));
+			Self::try_combine_decryption(&ciphertext_id);
+
+			Ok(())
+		}
+
+		/// Starts a proactive resharing into `new_authorities`: a fresh committee takes
+		/// over the shares without ever changing `MasterPublicKey`. No-op while a
+		/// resharing is already in progress.
+		#[weight = 0]
+		pub fn start_resharing(origin, new_authorities: Vec<T::AuthorityId>) {
+			let _who = ensure_signed(origin)?;
+			debug::RuntimeLogger::init();
+
+			if <NextAuthorities<T>>::get().is_empty() {
+				debug::info!("DKG START_RESHARING CALL: starting resharing into {:?}", new_authorities);
+				<NextAuthorities<T>>::put(new_authorities);
 			} else {
-				EncryptedSharesLists::insert(ix, shares);
-				CommittedPolynomials::insert(ix, comm_poly);
+				debug::info!("DKG START_RESHARING CALL: a resharing is already in progress");
+			}
+		}
+
+		#[weight = 0]
+		pub fn post_resharing_encryption_key(origin, epoch: u32, pk: EncryptionPublicKey, new_ix: AuthIndex) {
+			let _who = ensure_signed(origin)?;
+			debug::RuntimeLogger::init();
+			debug::info!("DKG POST_RESHARING_ENCRYPTION_KEY CALL: epoch {:?} new_ix {:?}", epoch, new_ix);
+
+			if epoch == CurrentEpoch::get() + 1 {
+				ResharingEncryptionPKs::insert(epoch, new_ix, pk);
 			}
 		}
 
 		#[weight = 0]
-		pub fn round2(origin, disputes: Vec<Vec<u8>>, hash_round1: T::Hash) {
+		pub fn post_resharing(origin, epoch: u32, shares: Vec<Vec<u8>>, comm_poly: Vec<Commitment>, old_ix: AuthIndex) {
 			let _who = ensure_signed(origin)?;
-			// logic for receiving round2 tx
+			debug::RuntimeLogger::init();
+			debug::info!("DKG POST_RESHARING CALL: epoch {:?} old_ix {:?}", epoch, old_ix);
+
+			if epoch == CurrentEpoch::get() + 1 {
+				ResharingShares::insert(epoch, old_ix, shares);
+				ResharingPolynomials::insert(epoch, old_ix, comm_poly);
+			}
+		}
+
+		/// Checks that the dealings posted so far for `epoch` recombine (via the same
+		/// Lagrange coefficients used to reconstruct shares, applied in the exponent to
+		/// the dealings' constant-term commitments) to the unchanged `MasterPublicKey`,
+		/// and if so promotes `NextAuthorities` to `Authorities` and advances the epoch.
+		#[weight = 0]
+		pub fn finalize_resharing(origin, epoch: u32) {
+			let _who = ensure_signed(origin)?;
+			debug::RuntimeLogger::init();
+
+			if epoch != CurrentEpoch::get() + 1 || <NextAuthorities<T>>::get().is_empty() {
+				debug::info!("DKG FINALIZE_RESHARING CALL: no resharing into epoch {:?} in progress", epoch);
+			} else if let Some(master_key) = MasterPublicKey::get() {
+				let threshold = Threshold::get() as usize;
+				let n_members = <Authorities<T>>::get().len() as u64;
+				let dealers: Vec<AuthIndex> = (0..n_members)
+					.filter(|dealer| ResharingPolynomials::contains_key(epoch, dealer))
+					.collect();
+
+				if dealers.len() < threshold {
+					debug::info!("DKG FINALIZE_RESHARING CALL: not enough dealings yet for epoch {:?}", epoch);
+				} else {
+					let quorum: Vec<AuthIndex> = dealers.into_iter().take(threshold).collect();
+					let recombined = quorum
+						.iter()
+						.map(|&dealer| {
+							let lambda = Self::lagrange_coefficient(&quorum, dealer);
+							Self::resharing_polynomials(epoch, dealer)[0] * lambda
+						})
+						.fold(Commitment::identity(), |acc, term| acc + term);
+
+					if recombined != master_key {
+						debug::info!(
+							"DKG FINALIZE_RESHARING CALL: resharing for epoch {:?} does not preserve MasterPublicKey",
+							epoch,
+						);
+					} else {
+						debug::info!("DKG FINALIZE_RESHARING CALL: epoch {:?} finalized", epoch);
+
+						// `verification_key`/`try_combine_signature` read `CommittedPolynomials`
+						// + `IsCorrectDealer` against whatever `Authorities` currently is, so
+						// both must be re-expressed in terms of the quorum that just reshared
+						// the secret: scaling each dealer's committed polynomial by its own
+						// Lagrange coefficient makes the existing (unweighted) `Σ_dealer
+						// evaluate_commitments(..., ix)` reconstruct exactly the `new_sk`
+						// `finalize_own_resharing_share` computed for member `ix`. This assumes
+						// the committee size is unchanged across the resharing, same as the
+						// rest of the resharing storage (`ResharingEncryptionPKs` etc.), which
+						// is addressed by position in `NextAuthorities`.
+						for &dealer in &quorum {
+							let lambda = Self::lagrange_coefficient(&quorum, dealer);
+							let scaled: Vec<Commitment> = Self::resharing_polynomials(epoch, dealer)
+								.iter()
+								.map(|c| *c * lambda)
+								.collect();
+							CommittedPolynomials::insert(dealer, scaled);
+						}
+						let is_correct: Vec<bool> =
+							(0..n_members).map(|dealer| quorum.contains(&dealer)).collect();
+						IsCorrectDealer::put(is_correct);
+
+						let new_n_members = <NextAuthorities<T>>::get().len();
+						if (threshold as usize) > new_n_members {
+							// `Threshold` is carried over unchanged -- resharing re-shares the
+							// same secret at the same threshold, it doesn't renegotiate one --
+							// but it can only keep making sense if the new committee is still
+							// large enough to meet it.
+							debug::error!(
+								"DKG FINALIZE_RESHARING CALL: epoch {:?} leaves Threshold {:?} unreachable with {:?} new members",
+								epoch,
+								threshold,
+								new_n_members,
+							);
+						}
+
+						<Authorities<T>>::put(<NextAuthorities<T>>::get());
+						<NextAuthorities<T>>::kill();
+						CurrentEpoch::put(epoch);
+					}
+				}
+			}
+		}
+
+		fn on_initialize(block_number: T::BlockNumber) -> frame_support::weights::Weight {
+			if block_number == T::EndRound0::get() {
+				Self::deposit_event(Event::RoundEnded(0));
+				Self::deposit_event(Event::RoundStarted(1));
+				CurrentRound::put(1);
+			} else if block_number == T::EndRound1::get() {
+				Self::deposit_event(Event::RoundEnded(1));
+				Self::deposit_event(Event::RoundStarted(2));
+				CurrentRound::put(2);
+			} else if block_number == T::EndRound2::get() {
+				Self::deposit_event(Event::RoundEnded(2));
+				Self::deposit_event(Event::RoundStarted(3));
+				CurrentRound::put(3);
+			} else if block_number == T::EndRound3::get() {
+				Self::deposit_event(Event::RoundEnded(3));
+				CurrentRound::put(4);
+			}
+
+			0
 		}
 
 		fn offchain_worker(block_number: T::BlockNumber) {
 			debug::info!("DKG Hello World from offchain workers!");
 
-			if block_number < END_ROUND_0.into()  {
+			if T::SimplPedPoP::get() {
+				if block_number < T::EndRound0::get() {
+					Self::handle_simple_pedpop(block_number);
+				} else {
+					Self::finalize_simple_pedpop(block_number);
+					Self::handle_resharing(block_number);
+				}
+				return;
+			}
+
+			if block_number < T::EndRound0::get()  {
 					Self::handle_round0(block_number);
-			} else if block_number < END_ROUND_1.into() {
+			} else if block_number < T::EndRound1::get() {
 				// implement creating tx for round 1
 					Self::handle_round1(block_number);
-			} else if block_number < END_ROUND_2.into() {
+			} else if block_number < T::EndRound2::get() {
 				// implement creating tx for round 2
 					Self::handle_round2(block_number);
+			} else if block_number < T::EndRound3::get() {
+					Self::handle_round3(block_number);
+			} else {
+					Self::handle_resharing(block_number);
 			}
 		}
 	}
@@ -246,12 +773,18 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
-	fn set_threshold(threshold: u32) {
+	fn initialize_authority_accounts(accounts: &[T::AccountId]) -> Result<(), &'static str> {
+		if accounts.len() != Self::authorities().len() {
+			return Err("DKG: authority_accounts must have exactly one entry per authority");
+		}
+		debug::info!("DKG GENESIS initialize_authority_accounts {:?}", accounts);
+		<AuthorityAccounts<T>>::put(accounts.to_vec());
+		Ok(())
+	}
+
+	fn set_threshold(threshold: u32) -> Result<(), Error<T>> {
 		let n_members = Self::authorities().len();
-		assert!(
-			0 < threshold && threshold <= n_members as u32,
-			"Wrong threshold or n_members"
-		);
+		ensure!(0 < threshold && threshold <= n_members as u32, Error::<T>::InvalidThreshold);
 		debug::info!(
 			"DKG GENESIS set_threshold {:?} when n_members {:?}",
 			threshold,
@@ -260,6 +793,22 @@ impl<T: Trait> Module<T> {
 
 		assert!(!Threshold::exists(), "Threshold is already initialized!");
 		Threshold::set(threshold);
+		Ok(())
+	}
+
+	fn initialize_encryption_pks(encryption_pks: &[EncryptionPublicKey]) -> Result<(), &'static str> {
+		if encryption_pks.is_empty() {
+			return Ok(());
+		}
+
+		if encryption_pks.len() != Self::authorities().len() {
+			return Err("DKG: encryption_pks must have one entry per authority");
+		}
+		debug::info!("DKG GENESIS initialize_encryption_pks {:?}", encryption_pks);
+		for (ix, pk) in encryption_pks.iter().enumerate() {
+			EncryptionPKs::insert(ix as AuthIndex, pk.clone());
+		}
+		Ok(())
 	}
 
 	fn handle_round0(block_number: T::BlockNumber) {
@@ -320,8 +869,27 @@ impl<T: Trait> Module<T> {
 			.unwrap()
 	}
 
+	// Rejects `ix` unless `who` -- the account that signed the extrinsic -- really is
+	// the authority at that index, so a submission can't be spoofed or misattributed to
+	// another authority. Unlike `authority_index` (only ever called from the offchain
+	// worker against an account it already knows is a local authority), this must fail
+	// cleanly instead of panicking when `who` is not an authority at all.
+	fn ensure_own_authority_index(who: T::AccountId, ix: AuthIndex) -> DispatchResult {
+		ensure!(
+			<AuthorityAccounts<T>>::get().get(ix as usize) == Some(&who),
+			Error::<T>::UnknownAuthorityIndex
+		);
+		Ok(())
+	}
+
 	fn _local_authority_keys() -> impl Iterator<Item = (u32, T::AuthorityId)> {
-		let authorities = <Authorities<T>>::get();
+		Self::local_keys_among(<Authorities<T>>::get())
+	}
+
+	// Which of `authorities`' entries this node holds the private key for, alongside
+	// its index in `authorities` -- shared by `_local_authority_keys` (against the
+	// current committee) and `handle_resharing` (against `NextAuthorities`).
+	fn local_keys_among(authorities: Vec<T::AuthorityId>) -> impl Iterator<Item = (u32, T::AuthorityId)> {
 		let local_keys = T::AuthorityId::all();
 
 		authorities
@@ -336,6 +904,119 @@ impl<T: Trait> Module<T> {
 			})
 	}
 
+	// The `Trait::SimplPedPoP` counterpart of `handle_round0` + `handle_round1` combined:
+	// recipients' encryption keys come from genesis rather than a broadcast, so a dealer
+	// can generate its polynomial, encrypt every recipient's share and submit everything
+	// as a single `deal_simple_pedpop` transaction.
+	fn handle_simple_pedpop(block_number: T::BlockNumber) {
+		debug::info!("DKG handle_simple_pedpop called at block: {:?}", block_number);
+		const ALREADY_SET: () = ();
+
+		let own_ix = match Self::_local_authority_keys().next() {
+			Some((ix, _)) => ix as AuthIndex,
+			None => return,
+		};
+
+		let n_members = <Authorities<T>>::get().len() as u64;
+		let threshold = Threshold::get();
+
+		let val = StorageValueRef::persistent(b"dkw::secret_poly");
+		let res = val.mutate(|last_set: Option<Option<Vec<[u64; 4]>>>| match last_set {
+			Some(Some(_)) => Err(ALREADY_SET),
+			_ => {
+				let poly = gen_poly_coeffs(threshold - 1);
+				debug::info!("DKG generating secret polynomial");
+				Ok(poly)
+			}
+		});
+
+		// TODO: meh borrow checker
+		if res.is_err() {
+			return;
+		}
+		let res = res.unwrap();
+		if res.is_err() {
+			return;
+		}
+		let poly: Vec<Scalar> = res.unwrap().into_iter().map(Scalar::from_raw).collect();
+
+		// Unlike `handle_round0`/`handle_round1`, SimplPedPoP mode has no broadcast round
+		// for this key: `config(encryption_pks)` already holds every member's public key at
+		// genesis, so the matching secret has to be in local storage under `dkw::enc_key`
+		// beforehand. It is never generated here, since a fresh key could not match the
+		// genesis entry; a node without it does not deal.
+		let raw_secret = match StorageValueRef::persistent(b"dkw::enc_key").get() {
+			Some(Some(raw)) => raw,
+			_ => {
+				debug::info!("DKG handle_simple_pedpop: no local encryption key, nothing to deal with");
+				return;
+			}
+		};
+		let secret = Scalar::from_raw(raw_secret);
+
+		let shares: Vec<Vec<u8>> = (0..n_members)
+			.map(|recipient| {
+				let recipient_pk = Self::encryption_pks(recipient);
+				let enc_key = recipient_pk.to_encryption_key(secret);
+				let x = Scalar::from_raw([recipient + 1, 0, 0, 0]);
+				let share = poly_eval(&poly, &x);
+				enc_key.encrypt(&share.to_bytes().to_vec())
+			})
+			.collect();
+		let comms: Vec<Commitment> = poly.iter().map(|a| Commitment::new(*a)).collect();
+
+		let session_id = <frame_system::Module<T>>::block_hash(T::BlockNumber::default());
+		let mut context = own_ix.encode();
+		context.extend(session_id.encode());
+		let pop = SchnorrProof::generate(&poly[0], &context);
+
+		let signer = Signer::<T, T::AuthorityId>::all_accounts();
+		if !signer.can_sign() {
+			debug::info!("DKG ERROR NO KEYS FOR SIGNER!!!");
+		}
+		let tx_res = signer.send_signed_transaction(|_account| {
+			Call::deal_simple_pedpop(comms.clone(), shares.clone(), own_ix, pop.clone())
+		});
+
+		for (acc, res) in &tx_res {
+			match res {
+				Ok(()) => debug::info!("DKG sending SimplPedPoP dealing by [{:?}]", acc.id),
+				Err(e) => debug::error!(
+					"DKG [{:?}] Failed to submit SimplPedPoP dealing: {:?}",
+					acc.id,
+					e
+				),
+			}
+		}
+
+	}
+
+	// Submits `finalize_round3` once the SimplPedPoP dealing window (`EndRound0`) has
+	// closed, so the summed `MasterPublicKey` covers every dealer who qualified rather
+	// than whichever strict subset happened to land first -- `finalize_round3` itself
+	// rejects the call before the window closes, this just avoids spamming it while that
+	// would always fail.
+	fn finalize_simple_pedpop(block_number: T::BlockNumber) {
+		if MasterPublicKey::get().is_none() {
+			debug::info!("DKG finalize_simple_pedpop called at block: {:?}", block_number);
+			let signer = Signer::<T, T::AuthorityId>::all_accounts();
+			if !signer.can_sign() {
+				debug::info!("DKG ERROR NO KEYS FOR SIGNER!!!");
+			}
+			let tx_res = signer.send_signed_transaction(|_account| Call::finalize_round3());
+			for (acc, res) in &tx_res {
+				match res {
+					Ok(()) => debug::info!("DKG sending finalize_round3 by [{:?}]", acc.id),
+					Err(e) => debug::error!(
+						"DKG [{:?}] Failed to submit finalize_round3 transaction: {:?}",
+						acc.id,
+						e
+					),
+				}
+			}
+		}
+	}
+
 	fn handle_round1(block_number: T::BlockNumber) {
 		debug::info!("DKG handle_round1 called at block: {:?}", block_number);
 		const ALREADY_SET: () = ();
@@ -383,6 +1064,10 @@ impl<T: Trait> Module<T> {
 		}
 
 		// 2. generate secret shares
+		// Recipient-indexed, not compacted: `handle_round2`/`handle_round3` read their
+		// own share back via `enc_shares[own_ix]`, so a missing encryption key must leave
+		// a placeholder at that position rather than shifting every later recipient's
+		// share down by one.
 		let mut enc_shares = Vec::new();
 
 		for id in 0..n_members {
@@ -391,6 +1076,8 @@ impl<T: Trait> Module<T> {
 				let share = poly_eval(poly, x);
 				let share_data = share.to_bytes().to_vec();
 				enc_shares.push(enc_key.encrypt(&share_data));
+			} else {
+				enc_shares.push(Vec::new());
 			}
 		}
 
@@ -401,8 +1088,7 @@ impl<T: Trait> Module<T> {
 		}
 
 		// 4. send encrypted secret shares
-		let round0_number: T::BlockNumber = END_ROUND_0.into();
-		let hash_round0 = <frame_system::Module<T>>::block_hash(round0_number);
+		let hash_round0 = <frame_system::Module<T>>::block_hash(T::EndRound0::get());
 		let signer = Signer::<T, T::AuthorityId>::all_accounts();
 		if !signer.can_sign() {
 			debug::info!("DKG ERROR NO KEYS FOR SIGNER!!!");
@@ -430,6 +1116,511 @@ impl<T: Trait> Module<T> {
 
 	fn handle_round2(block_number: T::BlockNumber) {
 		debug::info!("DKG handle_round2 called at block: {:?}", block_number);
+
+		let own_ix = match Self::_local_authority_keys().next() {
+			Some((ix, _)) => ix as AuthIndex,
+			None => return,
+		};
+
+		let raw_secret = match StorageValueRef::persistent(b"dkw::enc_key").get() {
+			Some(Some(raw)) => raw,
+			_ => {
+				debug::info!("DKG handle_round2: no local encryption key, nothing to verify");
+				return;
+			}
+		};
+		let secret = Scalar::from_raw(raw_secret);
+		let own_pk = EncryptionPublicKey::from_raw_scalar(raw_secret);
+
+		let n_members = <Authorities<T>>::get().len() as u64;
+		let mut disputes = Vec::new();
+
+		for dealer in 0..n_members {
+			if dealer == own_ix
+				|| !EncryptionPKs::contains_key(dealer)
+				|| !EncryptedSharesLists::contains_key(dealer)
+				|| !CommittedPolynomials::contains_key(dealer)
+			{
+				continue;
+			}
+
+			let enc_shares = Self::encrypted_shares_lists(dealer);
+			let enc_data = match enc_shares.get(own_ix as usize) {
+				Some(data) if !data.is_empty() => data,
+				_ => continue,
+			};
+
+			let dealer_pk = Self::encryption_pks(dealer);
+			let shared_key = dealer_pk.to_encryption_key(secret);
+			let share = match shared_key
+				.decrypt(enc_data)
+				.and_then(|bytes| Scalar::from_bytes(&bytes).into())
+			{
+				Some(share) => share,
+				None => {
+					debug::info!("DKG handle_round2: share from dealer {:?} did not decrypt", dealer);
+					continue;
+				}
+			};
+
+			let commitments = Self::committed_polynomilas(dealer);
+			if Self::check_share_against_commitments(&commitments, own_ix, &share) {
+				continue;
+			}
+
+			debug::info!(
+				"DKG handle_round2: share from dealer {:?} failed the commitment check, filing a complaint",
+				dealer,
+			);
+			let dleq_proof = DleqProof::generate(&secret, &own_pk, &dealer_pk, &shared_key);
+			disputes.push(DisputeAgainstDealer {
+				dealer,
+				complainant: own_ix,
+				share,
+				shared_key,
+				dleq_proof,
+			});
+		}
+
+		if disputes.is_empty() {
+			return;
+		}
+
+		let hash_round1 = <frame_system::Module<T>>::block_hash(T::EndRound1::get());
+		let signer = Signer::<T, T::AuthorityId>::all_accounts();
+		if !signer.can_sign() {
+			debug::info!("DKG ERROR NO KEYS FOR SIGNER!!!");
+		}
+		let tx_res =
+			signer.send_signed_transaction(|_account| Call::round2(disputes.clone(), hash_round1));
+
+		for (acc, res) in &tx_res {
+			match res {
+				Ok(()) => debug::info!("DKG sending round2 disputes by [{:?}]", acc.id),
+				Err(e) => debug::error!(
+					"DKG [{:?}] Failed to submit transaction with round2 disputes: {:?}",
+					acc.id,
+					e
+				),
+			}
+		}
+	}
+
+	fn ensure_is_correct_dealer_initialized() {
+		if !IsCorrectDealer::exists() {
+			let n_members = <Authorities<T>>::get().len() as u64;
+			let initial: Vec<bool> = (0..n_members)
+				.map(|ix| EncryptionPKs::contains_key(ix) && CommittedPolynomials::contains_key(ix))
+				.collect();
+			IsCorrectDealer::put(initial);
+		}
+	}
+
+	// A dispute is upheld iff the share/shared_key it carries are honestly derived (the
+	// DLEQ proof checks out) and yet fail the Feldman commitment check against the
+	// dealer's committed polynomial -- i.e. the dealer really did send a bad share.
+	fn check_dispute(dispute: &DisputeAgainstDealer) -> bool {
+		if !EncryptionPKs::contains_key(dispute.dealer) || !EncryptionPKs::contains_key(dispute.complainant) {
+			return false;
+		}
+		if !CommittedPolynomials::contains_key(dispute.dealer) {
+			return false;
+		}
+
+		let dealer_pk = Self::encryption_pks(dispute.dealer);
+		let complainant_pk = Self::encryption_pks(dispute.complainant);
+		if !dispute
+			.dleq_proof
+			.verify(&complainant_pk, &dealer_pk, &dispute.shared_key)
+		{
+			return false;
+		}
+
+		let commitments = Self::committed_polynomilas(dispute.dealer);
+		!Self::check_share_against_commitments(&commitments, dispute.complainant, &dispute.share)
+	}
+
+	fn check_share_against_commitments(
+		commitments: &[Commitment],
+		recipient: AuthIndex,
+		share: &Scalar,
+	) -> bool {
+		Commitment::new(*share) == Self::evaluate_commitments(commitments, recipient)
+	}
+
+	// `g * p(recipient)`, evaluated homomorphically from a dealer's Feldman commitments
+	// alone -- this is what lets a complaint be checked, or a member's verification key
+	// derived, without ever learning the dealer's polynomial.
+	fn evaluate_commitments(commitments: &[Commitment], recipient: AuthIndex) -> Commitment {
+		let x = Scalar::from_raw([recipient + 1, 0, 0, 0]);
+		let mut power = Scalar::one();
+		let mut acc = Commitment::identity();
+		for c in commitments {
+			acc = acc + *c * power;
+			power *= x;
+		}
+		acc
+	}
+
+	/// Member `ix`'s verification key `Σ_dealer (g * p_dealer(ix))`, aggregated over the
+	/// dealers `IsCorrectDealer` marks honest -- the public counterpart of `ix`'s final
+	/// secret-key share, mirroring how `MasterPublicKey` aggregates the constant terms.
+	fn verification_key(ix: AuthIndex) -> Commitment {
+		let is_correct = IsCorrectDealer::get();
+		let n_members = <Authorities<T>>::get().len() as u64;
+		(0..n_members)
+			.filter(|dealer| is_correct.get(*dealer as usize).copied().unwrap_or(false))
+			.map(|dealer| Self::evaluate_commitments(&Self::committed_polynomilas(dealer), ix))
+			.fold(Commitment::identity(), |acc, term| acc + term)
+	}
+
+	fn handle_round3(block_number: T::BlockNumber) {
+		debug::info!("DKG handle_round3 called at block: {:?}", block_number);
+
+		if MasterPublicKey::get().is_none() {
+			let signer = Signer::<T, T::AuthorityId>::all_accounts();
+			if !signer.can_sign() {
+				debug::info!("DKG ERROR NO KEYS FOR SIGNER!!!");
+			}
+			let tx_res = signer.send_signed_transaction(|_account| Call::finalize_round3());
+
+			for (acc, res) in &tx_res {
+				match res {
+					Ok(()) => debug::info!("DKG sending finalize_round3 by [{:?}]", acc.id),
+					Err(e) => debug::error!(
+						"DKG [{:?}] Failed to submit finalize_round3 transaction: {:?}",
+						acc.id,
+						e
+					),
+				}
+			}
+		}
+
+		const ALREADY_SET: () = ();
+		let own_ix = Self::_local_authority_keys().next().map(|(ix, _)| ix as AuthIndex);
+		let raw_secret = StorageValueRef::persistent(b"dkw::enc_key").get();
+
+		if let (Some(own_ix), Some(Some(raw_secret))) = (own_ix, raw_secret) {
+			let secret = Scalar::from_raw(raw_secret);
+			let is_correct = IsCorrectDealer::get();
+			let n_members = <Authorities<T>>::get().len() as u64;
+
+			let val = StorageValueRef::persistent(b"dkw::final_share");
+			let _ = val.mutate(|last_set: Option<Option<[u64; 4]>>| match last_set {
+				Some(Some(_)) => Err(ALREADY_SET),
+				_ => {
+					let mut sk = Scalar::zero();
+					for dealer in 0..n_members {
+						if !is_correct.get(dealer as usize).copied().unwrap_or(false) {
+							continue;
+						}
+						if !EncryptedSharesLists::contains_key(dealer) {
+							continue;
+						}
+						let enc_shares = Self::encrypted_shares_lists(dealer);
+						let enc_data = match enc_shares.get(own_ix as usize) {
+							Some(data) if !data.is_empty() => data,
+							_ => continue,
+						};
+						let dealer_pk = Self::encryption_pks(dealer);
+						let shared_key = dealer_pk.to_encryption_key(secret);
+						if let Some(share) =
+							shared_key.decrypt(enc_data).and_then(|bytes| Scalar::from_bytes(&bytes).into())
+						{
+							sk += share;
+						}
+					}
+
+					debug::info!("DKG handle_round3: finalized own secret-key share");
+					Ok(sk.to_raw())
+				}
+			});
+		}
+	}
+
+	/// Combines `Threshold` distinct partial signatures submitted so far for `message`,
+	/// if there are that many, and stores the result in `CombinedSignatures` if it
+	/// verifies against `MasterPublicKey`.
+	fn try_combine_signature(message: &Vec<u8>) {
+		let master_key = match MasterPublicKey::get() {
+			Some(master_key) => master_key,
+			None => return,
+		};
+
+		let shares = Self::partial_signatures(message);
+		let mut distinct: Vec<AuthIndex> = shares.iter().map(|(ix, _)| *ix).collect();
+		distinct.sort_unstable();
+		distinct.dedup();
+
+		let threshold = Threshold::get() as usize;
+		if distinct.len() < threshold {
+			return;
+		}
+
+		let chosen: Vec<AuthIndex> = distinct.into_iter().take(threshold).collect();
+		let chosen_shares = chosen.iter().map(|ix| {
+			shares.iter().find(|(j, _)| j == ix).expect("ix is taken from shares; qed")
+		});
+		let combined = Self::combine_signature_shares(chosen_shares);
+
+		if combined.verify(&master_key, message) {
+			CombinedSignatures::insert(message, combined);
+		} else {
+			debug::info!(
+				"DKG try_combine_signature: combined signature for {:?} failed verification",
+				message,
+			);
+		}
+	}
+
+	/// Combines `Threshold` distinct, verified decryption shares submitted so far for
+	/// `ciphertext_id`, if there are that many, into `c1^sk` -- by the same Lagrange
+	/// interpolation "in the exponent" `combine_signature_shares` uses -- and recovers
+	/// the plaintext group element `c2 - c1^sk` into `Decryptions`.
+	fn try_combine_decryption(ciphertext_id: &Vec<u8>) {
+		let shares = Self::decryption_shares(ciphertext_id);
+		let mut distinct: Vec<AuthIndex> = shares.iter().map(|(ix, _)| *ix).collect();
+		distinct.sort_unstable();
+		distinct.dedup();
+
+		let threshold = Threshold::get() as usize;
+		if distinct.len() < threshold {
+			return;
+		}
+
+		let chosen: Vec<AuthIndex> = distinct.into_iter().take(threshold).collect();
+		let combined_c1_sk = chosen
+			.iter()
+			.map(|ix| {
+				let dec_share = shares
+					.iter()
+					.find(|(j, _)| j == ix)
+					.expect("ix is taken from shares; qed")
+					.1;
+				dec_share * Self::lagrange_coefficient(&chosen, *ix)
+			})
+			.fold(Commitment::identity(), |acc, term| acc + term);
+
+		let (_, c2) = Self::ciphertexts(ciphertext_id);
+		let minus_one = Scalar::zero() - Scalar::one();
+		let plaintext = c2 + combined_c1_sk * minus_one;
+		Decryptions::insert(ciphertext_id, plaintext);
+	}
+
+	// sigma = Σ_{i in S} lambda_i * sigma_i, the BLS threshold-signature reconstruction
+	// carried out "in the exponent" on the signature share group, mirroring how
+	// `sp-randomness-beacon` combines its own share signatures.
+	fn combine_signature_shares<'a>(
+		shares: impl Iterator<Item = &'a (AuthIndex, Signature)>,
+	) -> Signature {
+		let shares: Vec<&(AuthIndex, Signature)> = shares.collect();
+		let indices: Vec<AuthIndex> = shares.iter().map(|(ix, _)| *ix).collect();
+
+		let mut acc = Signature::identity();
+		for (ix, sig) in shares {
+			let lambda = Self::lagrange_coefficient(&indices, *ix);
+			acc = acc + *sig * lambda;
+		}
+		acc
+	}
+
+	// lambda_i = Π_{j != i} j / (j - i), computed over the 1-based member indices
+	// corresponding to `indices`.
+	fn lagrange_coefficient(indices: &[AuthIndex], i: AuthIndex) -> Scalar {
+		let xi = Scalar::from_raw([i + 1, 0, 0, 0]);
+		let mut num = Scalar::one();
+		let mut den = Scalar::one();
+		for &j in indices {
+			if j == i {
+				continue;
+			}
+			let xj = Scalar::from_raw([j + 1, 0, 0, 0]);
+			num *= xj;
+			den *= xj - xi;
+		}
+		num * den.invert().expect("distinct indices imply a non-zero denominator; qed")
+	}
+
+	// The key a shareholder's own final secret-key share is kept under: epoch 0 (the
+	// original DKG) reuses the unsuffixed key `handle_round3` already writes to, later
+	// epochs (each completed resharing) get their own suffixed key so an in-flight
+	// resharing never clobbers the share that is still in active use.
+	fn final_share_storage_key(epoch: u32) -> Vec<u8> {
+		let mut key = b"dkw::final_share".to_vec();
+		if epoch > 0 {
+			key.extend(epoch.encode());
+		}
+		key
+	}
+
+	fn handle_resharing(block_number: T::BlockNumber) {
+		let next_authorities = <NextAuthorities<T>>::get();
+		if next_authorities.is_empty() {
+			return;
+		}
+		debug::info!("DKG handle_resharing called at block: {:?}", block_number);
+
+		let epoch = CurrentEpoch::get() + 1;
+
+		// 1. announce a resharing-round encryption key for every new-committee seat we
+		// hold -- reusing whatever ECDH key we already have, there is no need for a
+		// fresh one.
+		for (new_ix, _) in Self::local_keys_among(next_authorities.clone()) {
+			let new_ix = new_ix as AuthIndex;
+			if ResharingEncryptionPKs::contains_key(epoch, new_ix) {
+				continue;
+			}
+			if let Some(Some(raw_secret)) = StorageValueRef::persistent(b"dkw::enc_key").get() {
+				let pk = EncryptionPublicKey::from_raw_scalar(raw_secret);
+				let signer = Signer::<T, T::AuthorityId>::all_accounts();
+				let tx_res = signer.send_signed_transaction(|_account| {
+					Call::post_resharing_encryption_key(epoch, pk.clone(), new_ix)
+				});
+				for (acc, res) in &tx_res {
+					match res {
+						Ok(()) => debug::info!("DKG sending resharing encryption key by [{:?}]", acc.id),
+						Err(e) => debug::error!(
+							"DKG [{:?}] Failed to submit resharing encryption key: {:?}",
+							acc.id,
+							e
+						),
+					}
+				}
+			}
+		}
+
+		// 2. if we are an old shareholder, deal a fresh polynomial whose constant term
+		// is our own final secret-key share
+		if let Some((old_ix, _)) = Self::_local_authority_keys().next() {
+			if !ResharingPolynomials::contains_key(epoch, old_ix) {
+				Self::deal_resharing(epoch, old_ix, &next_authorities);
+			}
+		}
+
+		// 3. if we are in the new committee, reconstruct our fresh share once enough
+		// old shareholders have dealt
+		if let Some((new_ix, _)) = Self::local_keys_among(next_authorities).next() {
+			Self::finalize_own_resharing_share(epoch, new_ix as AuthIndex);
+		}
+
+		// 4. try to move the committee over once the resharing looks complete
+		let signer = Signer::<T, T::AuthorityId>::all_accounts();
+		if !signer.can_sign() {
+			debug::info!("DKG ERROR NO KEYS FOR SIGNER!!!");
+		}
+		let tx_res = signer.send_signed_transaction(|_account| Call::finalize_resharing(epoch));
+		for (acc, res) in &tx_res {
+			match res {
+				Ok(()) => debug::info!("DKG sending finalize_resharing for epoch {:?} by [{:?}]", epoch, acc.id),
+				Err(e) => debug::error!(
+					"DKG [{:?}] Failed to submit finalize_resharing transaction: {:?}",
+					acc.id,
+					e
+				),
+			}
+		}
+	}
+
+	fn deal_resharing(epoch: u32, old_ix: AuthIndex, next_authorities: &[T::AuthorityId]) {
+		let raw_sk = match StorageValueRef::persistent(&Self::final_share_storage_key(CurrentEpoch::get()))
+			.get()
+		{
+			Some(Some(raw)) => raw,
+			_ => return,
+		};
+		let raw_secret = match StorageValueRef::persistent(b"dkw::enc_key").get() {
+			Some(Some(raw)) => raw,
+			_ => return,
+		};
+		let own_secret = Scalar::from_raw(raw_secret);
+
+		let threshold = Threshold::get();
+		let n_new_members = next_authorities.len() as u64;
+		if (0..n_new_members).any(|new_ix| !ResharingEncryptionPKs::contains_key(epoch, new_ix)) {
+			debug::info!(
+				"DKG deal_resharing: not every NextAuthorities member has announced an encryption key for epoch {:?} yet",
+				epoch,
+			);
+			return;
+		}
+
+		// poly(0) = our own final secret-key share, the rest of the coefficients are fresh
+		let mut poly = Vec::new();
+		poly.push(Scalar::from_raw(raw_sk));
+		poly.extend((1..threshold).map(|_| Scalar::from_raw(gen_raw_scalar())));
+
+		let shares: Vec<Vec<u8>> = (0..n_new_members)
+			.map(|new_ix| {
+				let new_pk = Self::resharing_encryption_pks(epoch, new_ix);
+				let enc_key = new_pk.to_encryption_key(own_secret);
+				let x = Scalar::from_raw([new_ix + 1, 0, 0, 0]);
+				let share = poly_eval(&poly, &x);
+				enc_key.encrypt(&share.to_bytes().to_vec())
+			})
+			.collect();
+		let comms: Vec<Commitment> = poly.iter().map(|a| Commitment::new(*a)).collect();
+
+		let signer = Signer::<T, T::AuthorityId>::all_accounts();
+		let tx_res = signer.send_signed_transaction(|_account| {
+			Call::post_resharing(epoch, shares.clone(), comms.clone(), old_ix)
+		});
+		for (acc, res) in &tx_res {
+			match res {
+				Ok(()) => debug::info!("DKG sending resharing dealing for epoch {:?} by [{:?}]", epoch, acc.id),
+				Err(e) => debug::error!(
+					"DKG [{:?}] Failed to submit resharing dealing: {:?}",
+					acc.id,
+					e
+				),
+			}
+		}
+	}
+
+	fn finalize_own_resharing_share(epoch: u32, new_ix: AuthIndex) {
+		const ALREADY_SET: () = ();
+
+		let raw_secret = match StorageValueRef::persistent(b"dkw::enc_key").get() {
+			Some(Some(raw)) => raw,
+			_ => return,
+		};
+		let own_secret = Scalar::from_raw(raw_secret);
+
+		let threshold = Threshold::get() as usize;
+		let n_members = <Authorities<T>>::get().len() as u64;
+		let dealers: Vec<AuthIndex> = (0..n_members)
+			.filter(|dealer| ResharingPolynomials::contains_key(epoch, dealer))
+			.collect();
+		if dealers.len() < threshold {
+			return;
+		}
+		let quorum: Vec<AuthIndex> = dealers.into_iter().take(threshold).collect();
+
+		let key = Self::final_share_storage_key(epoch);
+		let val = StorageValueRef::persistent(&key);
+		let _ = val.mutate(|last_set: Option<Option<[u64; 4]>>| match last_set {
+			Some(Some(_)) => Err(ALREADY_SET),
+			_ => {
+				let mut new_sk = Scalar::zero();
+				for &dealer in &quorum {
+					let shares = Self::resharing_shares(epoch, dealer);
+					let enc_data = shares.get(new_ix as usize).ok_or(ALREADY_SET)?;
+					let dealer_pk = Self::encryption_pks(dealer);
+					let shared_key = dealer_pk.to_encryption_key(own_secret);
+					let share = shared_key
+						.decrypt(enc_data)
+						.and_then(|bytes| Scalar::from_bytes(&bytes).into())
+						.ok_or(ALREADY_SET)?;
+					let lambda = Self::lagrange_coefficient(&quorum, dealer);
+					new_sk += share * lambda;
+				}
+
+				debug::info!(
+					"DKG finalize_own_resharing_share: finalized the secret-key share for epoch {:?}",
+					epoch,
+				);
+				Ok(new_sk.to_raw())
+			}
+		});
 	}
 }
 
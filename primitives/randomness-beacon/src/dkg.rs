@@ -0,0 +1,133 @@
+//! Distributed key generation for the randomness beacon.
+//!
+//! Produces the master key and per-member [`VerifyKey`]s jointly, via Feldman/Pedersen
+//! verifiable secret sharing, so no single party ever learns the master secret and tests
+//! no longer need to seed a [`KeyBox`] from the fixed [`MASTER_SEED`].
+//!
+//! Running a session is a handful of rounds: every participant [`deal`]s a polynomial,
+//! recipients [`verify_share`] what they were sent and raise a [`Complaint`] otherwise,
+//! and once complaints are resolved the qualified dealings are combined with
+//! [`finalize`] into a ready [`KeyBox`].
+
+use crate::{KeyBox, RandomnessVerifier, ShareProvider, VerifyKey};
+use bls12_381::{G1Affine, G1Projective, Scalar};
+use sp_core::crypto::Pair;
+use sp_std::vec::Vec;
+
+/// Feldman commitments `C_j = g * a_j` to the coefficients of a dealer's polynomial.
+#[derive(Clone)]
+pub struct Commitments(pub Vec<G1Affine>);
+
+impl Commitments {
+	/// `g * p(x)`, computed from the committed coefficients alone -- this lets a
+	/// recipient check its share without learning `p`.
+	fn evaluate(&self, x: u32) -> G1Projective {
+		let x = Scalar::from(x as u64);
+		let mut power = Scalar::one();
+		let mut acc = G1Projective::identity();
+		for c in &self.0 {
+			acc += G1Projective::from(c) * power;
+			power *= x;
+		}
+		acc
+	}
+
+	/// The constant term `C_0 = g * p(0)`, i.e. this dealer's contribution to the
+	/// master public key.
+	pub fn constant_term(&self) -> G1Affine {
+		self.0[0]
+	}
+}
+
+/// What a dealer broadcasts in the dealing round: its Feldman commitments, and one
+/// polynomial evaluation per recipient (`shares[i]` is `p(i + 1)`, sent to recipient
+/// `i + 1` over an encrypted channel -- encryption is out of scope of this module, which
+/// only implements the threshold-sharing math).
+#[derive(Clone)]
+pub struct Dealing {
+	pub dealer: u32,
+	pub commitments: Commitments,
+	shares: Vec<Scalar>,
+}
+
+/// A complaint filed by `complainant` against `dealer` whose share failed
+/// [`verify_share`].
+pub struct Complaint {
+	pub dealer: u32,
+	pub complainant: u32,
+}
+
+/// Samples a degree `threshold - 1` polynomial and deals it to `n_members` recipients.
+pub fn deal(dealer: u32, n_members: u32, threshold: u32) -> Dealing {
+	let coeffs: Vec<Scalar> = (0..threshold).map(|_| random_scalar()).collect();
+	let shares = (1..=n_members).map(|i| poly_eval(&coeffs, Scalar::from(i as u64))).collect();
+	let commitments =
+		Commitments(coeffs.iter().map(|a| (G1Projective::generator() * a).into()).collect());
+	Dealing { dealer, commitments, shares }
+}
+
+/// The evaluation this dealing sends to `recipient` (1-based).
+pub fn share_for(dealing: &Dealing, recipient: u32) -> Scalar {
+	dealing.shares[(recipient - 1) as usize]
+}
+
+/// Checks `g * p(recipient) == sum_j C_j * recipient^j`. A recipient for whom this
+/// fails must raise a [`Complaint`] instead of using the share.
+pub fn verify_share(commitments: &Commitments, recipient: u32, share: &Scalar) -> bool {
+	G1Projective::generator() * share == commitments.evaluate(recipient)
+}
+
+fn poly_eval(coeffs: &[Scalar], x: Scalar) -> Scalar {
+	let mut eval = Scalar::zero();
+	for c in coeffs.iter().rev() {
+		eval = eval * x + c;
+	}
+	eval
+}
+
+fn random_scalar() -> Scalar {
+	Scalar::from_bytes_wide(&rand::random())
+}
+
+/// Combines the qualified dealings (those against which no complaint was upheld) into
+/// the master public key and participant `id`'s final secret-key share, returning a
+/// ready-to-use [`KeyBox`] that holds the `VerifyKey` of every member -- the DKG analogue of
+/// the trusted-dealer setup that currently seeds from [`MASTER_SEED`].
+pub fn finalize(id: u32, qualified: &[Dealing], n_members: usize, threshold: usize) -> KeyBox {
+	let master_point: G1Projective =
+		qualified.iter().fold(G1Projective::identity(), |acc, d| acc + d.commitments.constant_term());
+	let master_key = RandomnessVerifier::new(encode_public(&master_point.into()));
+
+	let secret_share: Scalar =
+		qualified.iter().fold(Scalar::zero(), |acc, d| acc + share_for(d, id));
+	let share_provider = ShareProvider::from_seed(&secret_share.to_bytes());
+	// `from_seed` is specified by `Pair` only as "derive a key pair from this seed" --
+	// some backends (e.g. an EIP-2333-style IKM expansion) hash the seed rather than
+	// using it as the raw scalar, in which case `share_provider.public()` would silently
+	// diverge from the `g * secret_share` that `verify_keys` below (and every recipient's
+	// `verify_share`) is computed against. Assert the two agree rather than let that
+	// mismatch surface later as every honest share failing verification.
+	assert_eq!(
+		share_provider.public(),
+		encode_public(&(G1Projective::generator() * secret_share).into()),
+		"DKG: ShareProvider::from_seed did not reproduce g * secret_share from the raw \
+		 scalar bytes -- check whether the underlying Pair hashes/expands its seed",
+	);
+
+	let verify_keys = (1..=n_members as u32)
+		.map(|member| {
+			let point: G1Projective = qualified
+				.iter()
+				.fold(G1Projective::identity(), |acc, d| acc + d.commitments.evaluate(member));
+			encode_public(&point.into())
+		})
+		.collect();
+
+	KeyBox::new(id, share_provider, verify_keys, master_key, threshold)
+}
+
+fn encode_public(point: &G1Affine) -> VerifyKey {
+	use codec::Decode;
+	VerifyKey::decode(&mut &point.to_compressed()[..])
+		.expect("a compressed G1 point decodes into the raw public-key bytes; qed")
+}
@@ -0,0 +1,116 @@
+//! Carries the randomness beacon's output into blocks as inherent data.
+//!
+//! At authoring time, [`InherentDataProvider`] combines the current round's [`Share`]s
+//! into a [`Randomness`] value nonce-bound to the parent block and injects it as
+//! inherent data; [`check_inherent`] re-verifies that value against the `VerifyKey`
+//! installed via `RandomnessBeaconApi::set_randomness_verifier` when importing a block,
+//! so a block whose embedded randomness fails verification -- or whose nonce does not
+//! match the expected derivation for that height -- is rejected.
+
+use codec::{Decode, Encode};
+use sp_inherents::{InherentData, InherentIdentifier, IsFatalError};
+use sp_runtime::RuntimeString;
+
+use crate::{Nonce, Randomness, RandomnessVerifier};
+
+#[cfg(feature = "std")]
+use crate::{KeyBox, Share};
+#[cfg(feature = "std")]
+use sp_std::vec::Vec;
+
+pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"rndmbcn0";
+
+#[derive(Encode, Decode, sp_runtime::RuntimeDebug)]
+pub enum InherentError {
+	/// No randomness was found among the block's inherent data.
+	RandomnessNotAvailable,
+	/// The embedded randomness is nonce-bound to a different block than the one it was
+	/// included in.
+	WrongNonce,
+	/// The embedded randomness failed verification against the installed `VerifyKey`.
+	InvalidRandomness,
+}
+
+impl IsFatalError for InherentError {
+	fn is_fatal_error(&self) -> bool {
+		true
+	}
+}
+
+/// The nonce a block built on top of `parent_number`/`parent_hash` must use -- binding
+/// the randomness to a specific height stops a round's output from being replayed into
+/// a later block.
+pub fn expected_nonce<N: Encode, H: Encode>(parent_number: N, parent_hash: H) -> Nonce {
+	(parent_number, parent_hash).encode()
+}
+
+/// Re-runs beacon verification against the randomness embedded in `data`, rejecting it
+/// if it fails or if its nonce does not match the expected derivation for this height.
+pub fn check_inherent<N: Encode, H: Encode>(
+	verifier: &RandomnessVerifier,
+	data: &InherentData,
+	parent_number: N,
+	parent_hash: H,
+) -> Result<(), InherentError> {
+	let randomness: Randomness = data
+		.get_data(&INHERENT_IDENTIFIER)
+		.map_err(|_| InherentError::RandomnessNotAvailable)?
+		.ok_or(InherentError::RandomnessNotAvailable)?;
+
+	if randomness.nonce != expected_nonce(parent_number, parent_hash) {
+		return Err(InherentError::WrongNonce)
+	}
+
+	if !verifier.verify(randomness) {
+		return Err(InherentError::InvalidRandomness)
+	}
+
+	Ok(())
+}
+
+/// Builds the randomness inherent for the block currently being authored.
+#[cfg(feature = "std")]
+pub struct InherentDataProvider {
+	randomness: Randomness,
+}
+
+#[cfg(feature = "std")]
+impl InherentDataProvider {
+	/// Combines `shares` collected for the current round via `key_box`, binding the
+	/// result to the block built on top of `parent_number`/`parent_hash`.
+	pub fn new<N: Encode, H: Encode>(
+		key_box: &KeyBox,
+		shares: &Vec<Share>,
+		parent_number: N,
+		parent_hash: H,
+	) -> Result<Self, RuntimeString> {
+		let nonce = expected_nonce(parent_number, parent_hash);
+		if shares.iter().any(|s| s.nonce != nonce) {
+			return Err("beacon shares do not match the expected nonce for this block".into())
+		}
+
+		let randomness = key_box.combine_shares(shares).ok_or_else(|| {
+			RuntimeString::from("not enough valid beacon shares to combine randomness")
+		})?;
+
+		Ok(InherentDataProvider { randomness })
+	}
+}
+
+#[cfg(feature = "std")]
+impl sp_inherents::ProvideInherentData for InherentDataProvider {
+	fn inherent_identifier(&self) -> &'static InherentIdentifier {
+		&INHERENT_IDENTIFIER
+	}
+
+	fn provide_inherent_data(
+		&self,
+		inherent_data: &mut InherentData,
+	) -> Result<(), RuntimeString> {
+		inherent_data.put_data(INHERENT_IDENTIFIER, &self.randomness)
+	}
+
+	fn error_to_string(&self, mut error: &[u8]) -> Option<sp_std::string::String> {
+		InherentError::decode(&mut error).map(|e| sp_std::format!("{:?}", e)).ok()
+	}
+}
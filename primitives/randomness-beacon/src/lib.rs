@@ -1,4 +1,6 @@
 #![cfg_attr(not(feature = "std"), no_std)]
+#[cfg(feature = "std")]
+pub mod dkg;
 pub mod inherents;
 
 use codec::{Decode, Encode};
@@ -6,9 +8,11 @@ use codec::{Decode, Encode};
 use sp_core::crypto::Pair;
 use sp_std::vec::Vec;
 
+// BLS12-381 is pairing-friendly, which is what lets us combine individual member
+// signatures into a single group signature by Lagrange interpolation "in the exponent".
 pub mod app {
-	use sp_application_crypto::{app_crypto, ed25519, key_types::RANDOMNESS_BEACON};
-	app_crypto!(ed25519, RANDOMNESS_BEACON);
+	use sp_application_crypto::{app_crypto, bls381, key_types::RANDOMNESS_BEACON};
+	app_crypto!(bls381, RANDOMNESS_BEACON);
 }
 
 sp_application_crypto::with_pair! {
@@ -123,11 +127,19 @@ impl KeyBox {
 	}
 
 	pub fn verify_share(&self, share: &Share) -> bool {
-		ShareProvider::verify(
-			&share.data,
-			share.nonce.clone(),
-			&self.verify_keys[share.creator as usize],
-		)
+		// `creator` is the 1-based member id (0 is reserved for the master secret, see
+		// `combine_shares`), while `verify_keys` is the 0-based array `dkg::finalize`
+		// builds (`verify_keys[0]` is member 1's key). A creator outside 1..=n_members has
+		// no key, so its share fails verification.
+		let verify_key = match share
+			.creator
+			.checked_sub(1)
+			.and_then(|ix| self.verify_keys.get(ix as usize))
+		{
+			Some(verify_key) => verify_key,
+			None => return false,
+		};
+		ShareProvider::verify(&share.data, share.nonce.clone(), verify_key)
 	}
 
 	// Some(share) if succeeded and None if failed for some reason (e.g. not enough shares) -- should add error handling later
@@ -136,28 +148,49 @@ impl KeyBox {
 			return None;
 		}
 
-		if shares.iter().any(|s| !self.verify_share(s)) {
+		let nonce = shares[0].nonce.clone();
+		if shares.iter().any(|s| s.nonce != nonce) {
 			return None;
 		}
 
-		if shares
-			.iter()
-			.filter(|share| shares.iter().filter(|s| s == share).count() == 1)
-			.count() < self.threshold
-		{
+		// Only verified shares count towards the threshold -- a single invalid share
+		// (corrupted in transit, or crafted by a dishonest relayer) should be discarded,
+		// not silently corrupt the combination or let one bad share veto the rest.
+		let verified: Vec<&Share> = shares.iter().filter(|s| self.verify_share(s)).collect();
+
+		// Dedup by creator -- a dishonest relayer could repeat the same share to
+		// try to fake reaching the threshold.
+		let mut distinct_creators: Vec<u32> = verified.iter().map(|s| s.creator).collect();
+		distinct_creators.sort_unstable();
+		distinct_creators.dedup();
+		if distinct_creators.len() < self.threshold {
 			return None;
 		}
 
-		let nonce = shares[0].nonce.clone();
-		if shares.iter().any(|s| s.nonce != nonce) {
+		// Creator indices are the 1-based member ids used as Lagrange interpolation
+		// points -- 0 is reserved for the master secret and must never appear here.
+		if distinct_creators.iter().any(|creator| *creator == 0) {
 			return None;
 		}
 
-		// TODO: replace the following mock
-		Some(Randomness {
-			nonce: nonce.clone(),
-			data: app::Signature::default(),
-		})
+		let chosen: Vec<u32> = distinct_creators.into_iter().take(self.threshold).collect();
+		let chosen_shares = chosen.iter().map(|creator| {
+			verified
+				.iter()
+				.find(|s| s.creator == *creator)
+				.expect("creator is taken from verified shares; qed")
+		});
+
+		let data = combine_signature_shares(chosen_shares.map(|s| (s.creator, &s.data)));
+
+		// Defence in depth, mirroring the pallet's `try_combine_signature`: every input
+		// share was verified individually above, but confirm the interpolated
+		// combination itself verifies against `master_key` before handing it out.
+		if !self.verify_randomness(Randomness { nonce: nonce.clone(), data: data.clone() }) {
+			return None;
+		}
+
+		Some(Randomness { nonce, data })
 	}
 
 	pub fn verify_randomness(&self, randomness: Randomness) -> bool {
@@ -173,6 +206,65 @@ impl KeyBox {
 	}
 }
 
+// Lagrange interpolation at 0 of the degree-(threshold-1) polynomial implicitly
+// defined by the shares, carried out "in the exponent" on the signature group:
+// sigma = sum_{i in S} lambda_i * sigma_i, lambda_i = prod_{j in S, j != i} j / (j - i).
+//
+// Reconstructing at a point other than 0 would leak no secret (the shares are
+// public signatures, not the secret key shares themselves), but 0 is where the
+// master key lives, so it is the only point whose combination verifies against
+// `master_key`.
+#[cfg(feature = "std")]
+fn combine_signature_shares<'a>(
+	shares: impl Iterator<Item = (u32, &'a app::Signature)>,
+) -> app::Signature {
+	use bls12_381::{G2Affine, G2Projective, Scalar};
+
+	let shares: sp_std::vec::Vec<(u32, &app::Signature)> = shares.collect();
+	let indices: sp_std::vec::Vec<u32> = shares.iter().map(|(i, _)| *i).collect();
+
+	let mut acc = G2Projective::identity();
+	for (i, sig) in shares {
+		let point = decode_signature(sig);
+		let lambda = lagrange_coefficient(&indices, i);
+		acc += point * lambda;
+	}
+
+	encode_signature(&acc.into())
+}
+
+#[cfg(feature = "std")]
+fn lagrange_coefficient(indices: &[u32], i: u32) -> bls12_381::Scalar {
+	use bls12_381::Scalar;
+
+	let xi = Scalar::from(i as u64);
+	let mut num = Scalar::one();
+	let mut den = Scalar::one();
+	for &j in indices {
+		if j == i {
+			continue;
+		}
+		let xj = Scalar::from(j as u64);
+		num *= xj;
+		den *= xj - xi;
+	}
+	num * den.invert().expect("distinct indices imply a non-zero denominator; qed")
+}
+
+#[cfg(feature = "std")]
+fn decode_signature(sig: &app::Signature) -> bls12_381::G2Affine {
+	let bytes: [u8; 96] = AsRef::<[u8]>::as_ref(sig)
+		.try_into()
+		.expect("a BLS12-381 signature is 96 compressed bytes; qed");
+	bls12_381::G2Affine::from_compressed(&bytes).expect("signature share was already verified; qed")
+}
+
+#[cfg(feature = "std")]
+fn encode_signature(point: &bls12_381::G2Affine) -> app::Signature {
+	app::Signature::decode(&mut &point.to_compressed()[..])
+		.expect("a compressed G2 point decodes into the raw signature bytes; qed")
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -209,7 +301,9 @@ mod tests {
 		let seed = b"21372137213721372137213721372137";
 		let share_provider2 = ShareProvider::from_seed(seed);
 		let verify_keys = vec![share_provider1.public(), share_provider2.public()];
-		let id = 0;
+		// 1-based, matching `dkg::finalize`'s convention: `verify_keys[id - 1]` is member
+		// `id`'s key.
+		let id = 1;
 		let threshold = 1;
 		let keybox = KeyBox::new(id, share_provider1, verify_keys, verifier, threshold);
 
@@ -219,7 +313,7 @@ mod tests {
 		share.nonce = b"2137".to_vec();
 		assert!(!keybox.verify_share(&share));
 		share.nonce = b"1729".to_vec();
-		share.creator = 1;
+		share.creator = 2;
 		assert!(!keybox.verify_share(&share));
 	}
 }
@@ -18,15 +18,18 @@
 use crate::{build_executor, ensure_matching_spec, extract_code, full_extensions, local_spec, parse, state_machine_call_with_proof, SharedParams, LOG_TARGET, twox_128};
 use jsonrpsee::{
 	core::client::{Subscription, SubscriptionClientT},
-	ws_client::WsClientBuilder,
+	ws_client::{WsClient, WsClientBuilder},
 };
-use parity_scale_codec::Decode;
+use parity_scale_codec::{Decode, Encode};
 use remote_externalities::{rpc_api, Builder, Mode, OnlineConfig};
 use sc_executor::NativeExecutionDispatch;
 use sc_service::Configuration;
-use sp_core::H256;
-use sp_runtime::traits::{Block as BlockT, Header, NumberFor};
-use std::{fmt::Debug, str::FromStr};
+use sp_core::{storage::{well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX, Storage}, H256};
+use sp_runtime::traits::{AtLeast32BitUnsigned, Block as BlockT, Header, NumberFor, One};
+use std::{fmt::Debug, path::PathBuf, str::FromStr};
+
+/// The longest we'll wait between resubscription attempts.
+const MAX_RESUBSCRIBE_BACKOFF: std::time::Duration = std::time::Duration::from_secs(60);
 
 const SUB: &str = "chain_subscribeFinalizedHeads";
 const UN_SUB: &str = "chain_unsubscribeFinalizedHeads";
@@ -40,6 +43,157 @@ pub struct FollowChainCmd {
 
 	#[clap(long)]
 	checking: bool,
+
+	/// Compare the locally recomputed storage root against the one declared in the
+	/// block header, and on a mismatch report every storage key touched by the block
+	/// whose value diverges from the remote chain's.
+	#[clap(long)]
+	verify_state_root: bool,
+
+	/// When `--verify-state-root` finds a mismatch, stop following the chain instead
+	/// of continuing with the (now divergent) local state.
+	#[clap(long)]
+	halt_on_mismatch: bool,
+
+	/// Persist the working externalities to this path after every executed block, and
+	/// resume from it on startup instead of re-scraping the full remote state.
+	#[clap(long)]
+	snapshot_path: Option<PathBuf>,
+}
+
+/// Header of an on-disk snapshot, recorded alongside the raw storage pairs so a
+/// snapshot taken before a runtime upgrade is recognised as stale.
+#[derive(Encode, Decode)]
+struct SnapshotHeader<Block: BlockT> {
+	spec_name: Vec<u8>,
+	spec_version: u32,
+	block_number: NumberFor<Block>,
+	block_hash: Block::Hash,
+	state_version: u32,
+	/// Whether the top trie we snapshotted pointed at any child tries. We only persist
+	/// the top trie's pairs (see `save_snapshot`), so a snapshot taken on a chain with
+	/// child storage is missing data and must never be resumed from.
+	has_child_storage: bool,
+}
+
+/// Serializes the current backend plus the last executed block and spec version to
+/// `path`, overwriting any previous snapshot.
+///
+/// Only the top trie is persisted -- if `backend` has any child tries, `has_child_storage`
+/// is recorded `true` so the snapshot is refused on resume rather than silently dropping
+/// child storage (see the `has_child_storage` check in `load_snapshot`'s caller).
+fn save_snapshot<Block: BlockT<Hash = H256>>(
+	path: &std::path::Path,
+	backend: &impl sp_state_machine::Backend<<Block::Header as Header>::Hashing>,
+	block_number: NumberFor<Block>,
+	block_hash: Block::Hash,
+	spec_name: &str,
+	spec_version: u32,
+	state_version: u32,
+) -> std::io::Result<()> {
+	let pairs: Vec<(Vec<u8>, Vec<u8>)> = backend.pairs().collect();
+	let has_child_storage =
+		pairs.iter().any(|(key, _)| key.starts_with(DEFAULT_CHILD_STORAGE_KEY_PREFIX));
+
+	let header = SnapshotHeader::<Block> {
+		spec_name: spec_name.as_bytes().to_vec(),
+		spec_version,
+		block_number,
+		block_hash,
+		state_version,
+		has_child_storage,
+	};
+
+	let mut buf = header.encode();
+	buf.extend(pairs.encode());
+	std::fs::write(path, buf)
+}
+
+/// Loads a previously saved snapshot, if any.
+fn load_snapshot<Block: BlockT<Hash = H256>>(
+	path: &std::path::Path,
+) -> Option<(SnapshotHeader<Block>, Vec<(Vec<u8>, Vec<u8>)>)> {
+	let bytes = std::fs::read(path).ok()?;
+	let mut slice = &bytes[..];
+	let header = SnapshotHeader::<Block>::decode(&mut slice).ok()?;
+	let pairs = Vec::<(Vec<u8>, Vec<u8>)>::decode(&mut slice).ok()?;
+	Some((header, pairs))
+}
+
+/// A single storage key whose locally recomputed value does not match the remote
+/// chain's value at the block being verified.
+#[derive(Debug)]
+struct KeyDiff {
+	key: Vec<u8>,
+	local: Option<Vec<u8>>,
+	remote: Option<Vec<u8>>,
+}
+
+/// Fetches the remote value of every key touched while executing this block and
+/// reports those whose locally recomputed value disagrees, so operators can localize
+/// exactly which storage items a runtime change mispredicted.
+async fn diff_touched_keys<Block: BlockT<Hash = H256>>(
+	uri: &str,
+	at: Block::Hash,
+	backend: &impl sp_state_machine::Backend<<Block::Header as Header>::Hashing>,
+	touched_keys: impl Iterator<Item = Vec<u8>>,
+) -> Vec<KeyDiff> {
+	let mut diffs = Vec::new();
+	for key in touched_keys {
+		let local = backend.storage(&key).ok().flatten();
+		let remote = rpc_api::get_storage::<Block>(uri, key.clone(), Some(at)).await.ok().flatten();
+		if local != remote {
+			diffs.push(KeyDiff { key, local, remote });
+		}
+	}
+	diffs
+}
+
+/// Connects to `uri` and subscribes to finalized heads, retrying with exponential
+/// backoff instead of giving up on the first failure.
+async fn subscribe_finalized_heads<Block>(
+	uri: &str,
+) -> (WsClient, Subscription<Block::Header>)
+where
+	Block: BlockT,
+	Block::Header: serde::de::DeserializeOwned,
+{
+	let mut backoff = std::time::Duration::from_secs(1);
+	loop {
+		let attempt = async {
+			let client = WsClientBuilder::default()
+				.connection_timeout(std::time::Duration::new(20, 0))
+				.max_notifs_per_subscription(1024)
+				.max_request_body_size(u32::MAX)
+				.build(uri)
+				.await
+				.map_err(|e| format!("failed to connect to {}: {:?}", uri, e))?;
+
+			log::info!(target: LOG_TARGET, "subscribing to {:?} / {:?}", SUB, UN_SUB);
+			let subscription: Subscription<Block::Header> = client
+				.subscribe(SUB, None, UN_SUB)
+				.await
+				.map_err(|e| format!("failed to subscribe: {:?}", e))?;
+
+			Ok::<_, String>((client, subscription))
+		}
+		.await;
+
+		match attempt {
+			Ok(pair) => return pair,
+			Err(why) => {
+				log::warn!(
+					target: LOG_TARGET,
+					"could not (re)subscribe to {:?}: {}, retrying in {:?}",
+					uri,
+					why,
+					backoff,
+				);
+				tokio::time::sleep(backoff).await;
+				backoff = (backoff * 2).min(MAX_RESUBSCRIBE_BACKOFF);
+			},
+		}
+	}
 }
 
 pub(crate) async fn follow_chain<Block, ExecDispatch>(
@@ -52,23 +206,16 @@ where
 	Block::Hash: FromStr,
 	Block::Header: serde::de::DeserializeOwned,
 	<Block::Hash as FromStr>::Err: Debug,
-	NumberFor<Block>: FromStr,
+	NumberFor<Block>: FromStr + AtLeast32BitUnsigned,
 	<NumberFor<Block> as FromStr>::Err: Debug,
 	ExecDispatch: NativeExecutionDispatch + 'static,
 {
 	let mut maybe_state_ext = None;
+	// Tracks the number of the last block we actually executed, so a resumed session
+	// (from a snapshot, or after a subscription gap) knows where to pick up from.
+	let mut last_executed: Option<NumberFor<Block>> = None;
 
-	let client = WsClientBuilder::default()
-		.connection_timeout(std::time::Duration::new(20, 0))
-		.max_notifs_per_subscription(1024)
-		.max_request_body_size(u32::MAX)
-		.build(&command.uri)
-		.await
-		.unwrap();
-
-	log::info!(target: LOG_TARGET, "subscribing to {:?} / {:?}", SUB, UN_SUB);
-	let mut subscription: Subscription<Block::Header> =
-		client.subscribe(SUB, None, UN_SUB).await.unwrap();
+	let (mut client, mut subscription) = subscribe_finalized_heads::<Block>(&command.uri).await;
 
 	let (code_key, code) = extract_code(&config.chain_spec)?;
 	let executor = build_executor::<ExecDispatch>(&shared, &config);
@@ -78,8 +225,12 @@ where
 		let header = match subscription.next().await {
 			Some(Ok(header)) => header,
 			None => {
-				log::warn!("subscription closed");
-				break
+				log::warn!(target: LOG_TARGET, "subscription closed, resubscribing");
+				let (new_client, new_subscription) =
+					subscribe_finalized_heads::<Block>(&command.uri).await;
+				client = new_client;
+				subscription = new_subscription;
+				continue
 			},
 			Some(Err(why)) => {
 				log::warn!("subscription returned error: {:?}. Probably decoding has failed.", why);
@@ -90,7 +241,19 @@ where
 		let hash = header.hash();
 		let number = header.number();
 
-		let block = rpc_api::get_block::<Block, _>(&command.uri, hash).await.unwrap();
+		let block = match rpc_api::get_block::<Block, _>(&command.uri, hash).await {
+			Ok(block) => block,
+			Err(why) => {
+				log::warn!(
+					target: LOG_TARGET,
+					"failed to fetch block {:?} ({:?}): {:?}, skipping this notification",
+					number,
+					hash,
+					why,
+				);
+				continue
+			},
+		};
 
 		log::error!("number: {:?}, hash: {:?}", block.header().number(), block.header().hash());
 		log::error!("state root: {:?}", block.header().state_root());
@@ -108,19 +271,103 @@ where
 
 		// create an ext at the state of this block, whatever is the first subscription event.
 		if maybe_state_ext.is_none() {
-			let mut builder = Builder::<Block>::new().mode(Mode::Online(OnlineConfig {
-				transport: command.uri.clone().into(),
-				at: Some(*header.parent_hash()),
-				scrape_children: true,
-				..Default::default()
-			})).inject_hashed_key(
-					&[twox_128(b"System"), twox_128(b"LastRuntimeUpgrade")].concat(),
-				).inject_default_child_tree_prefix();
-
-			let new_ext = builder
-				// .inject_hashed_key_value(&[(code_key.clone(), code.clone())])
-				.build()
-				.await?;
+			let mut snapshot = command.snapshot_path.as_deref().and_then(|path| {
+				let (snapshot_header, pairs) = load_snapshot::<Block>(path)?;
+				if snapshot_header.spec_name != config.chain_spec.name().as_bytes() {
+					log::warn!(
+						target: LOG_TARGET,
+						"snapshot at {:?} is for a different chain ({:?}), ignoring it",
+						path,
+						String::from_utf8_lossy(&snapshot_header.spec_name),
+					);
+					return None
+				}
+				if snapshot_header.has_child_storage {
+					// `save_snapshot` only persists the top trie -- resuming from one taken
+					// on a chain with child storage would silently drop it and diverge from
+					// the real state root, so refuse it outright instead.
+					log::warn!(
+						target: LOG_TARGET,
+						"snapshot at {:?} was taken on a chain with child storage, which isn't snapshotted, ignoring it",
+						path,
+					);
+					return None
+				}
+				Some((snapshot_header, pairs))
+			});
+
+			// The snapshot is only useful if its block is still known to the node we are
+			// following, i.e. it really is an ancestor of the incoming finalized head.
+			if let Some((snapshot_header, _)) = &snapshot {
+				if rpc_api::get_block::<Block, _>(&command.uri, snapshot_header.block_hash).await.is_err() {
+					log::warn!(
+						target: LOG_TARGET,
+						"snapshotted block {:?} is no longer known to the node, falling back to a full scrape",
+						snapshot_header.block_hash,
+					);
+					snapshot = None;
+				}
+			}
+
+			// A runtime upgrade bumps `spec_version` (and can bump `state_version`) while
+			// leaving `spec_name` untouched, so the name check above alone would happily
+			// resume from a snapshot taken before an upgrade the chain has since gone
+			// through. Reject it too in that case, falling back to a full scrape.
+			if let Some((snapshot_header, _)) = &snapshot {
+				match rpc_api::get_runtime_version::<Block, _>(&command.uri, Some(hash)).await {
+					Ok(remote_version) => {
+						let remote_state_version = remote_version.state_version() as u32;
+						if snapshot_header.spec_version != remote_version.spec_version
+							|| snapshot_header.state_version != remote_state_version
+						{
+							log::warn!(
+								target: LOG_TARGET,
+								"snapshot was taken at spec_version {:?} / state_version {:?}, but the chain is now at {:?} / {:?}, falling back to a full scrape",
+								snapshot_header.spec_version,
+								snapshot_header.state_version,
+								remote_version.spec_version,
+								remote_state_version,
+							);
+							snapshot = None;
+						}
+					},
+					Err(why) => {
+						log::warn!(
+							target: LOG_TARGET,
+							"could not fetch the chain's current runtime version ({:?}), falling back to a full scrape",
+							why,
+						);
+						snapshot = None;
+					},
+				}
+			}
+
+			last_executed = snapshot.as_ref().map(|(h, _)| h.block_number);
+
+			let new_ext = if let Some((snapshot_header, pairs)) = snapshot {
+				log::info!(
+					target: LOG_TARGET,
+					"resuming from snapshot at block {:?}, will replay up to {:?}",
+					snapshot_header.block_number,
+					number,
+				);
+				let storage = Storage { top: pairs.into_iter().collect(), children_default: Default::default() };
+				remote_externalities::TestExternalities::new(storage)
+			} else {
+				let mut builder = Builder::<Block>::new().mode(Mode::Online(OnlineConfig {
+					transport: command.uri.clone().into(),
+					at: Some(*header.parent_hash()),
+					scrape_children: true,
+					..Default::default()
+				})).inject_hashed_key(
+						&[twox_128(b"System"), twox_128(b"LastRuntimeUpgrade")].concat(),
+					).inject_default_child_tree_prefix();
+
+				builder
+					// .inject_hashed_key_value(&[(code_key.clone(), code.clone())])
+					.build()
+					.await?
+			};
 			log::info!(
 				target: LOG_TARGET,
 				"initialized state externalities at {:?}, storage root {:?}",
@@ -138,12 +385,72 @@ where
 			)
 			.await;
 
-			maybe_state_ext = Some((new_ext, spec_state_version));
+			maybe_state_ext = Some((new_ext, spec_state_version, expected_spec_version));
 		}
 
-		let (state_ext, spec_state_version) =
+		let (state_ext, spec_state_version, spec_version) =
 			maybe_state_ext.as_mut().expect("state_ext either existed or was just created");
 
+		// The node may have skipped some finalized notifications under load, or we may
+		// just have resumed from an older snapshot -- either way, apply whatever blocks
+		// we are missing, in order, before moving on to `number`.
+		if let Some(last) = last_executed {
+			let mut missing = last + One::one();
+			while missing < *number {
+				log::info!(target: LOG_TARGET, "filling gap: executing missing block {:?}", missing);
+
+				let missing_hash = match rpc_api::get_block_hash::<Block, _>(&command.uri, missing).await {
+					Ok(hash) => hash,
+					Err(why) => {
+						log::warn!(
+							target: LOG_TARGET,
+							"could not fetch hash of gap block {:?}: {:?}, giving up on this gap",
+							missing,
+							why,
+						);
+						break
+					},
+				};
+				let missing_block = match rpc_api::get_block::<Block, _>(&command.uri, missing_hash).await
+				{
+					Ok(block) => block,
+					Err(why) => {
+						log::warn!(
+							target: LOG_TARGET,
+							"could not fetch gap block {:?}: {:?}, giving up on this gap",
+							missing,
+							why,
+						);
+						break
+					},
+				};
+
+				let gap_method = if command.checking {
+					"Core_execute_block"
+				} else {
+					"TryRuntime_execute_block_no_check"
+				};
+				let (mut gap_changes, _) = state_machine_call_with_proof::<Block, ExecDispatch>(
+					state_ext,
+					&executor,
+					execution,
+					gap_method,
+					missing_block.encode().as_ref(),
+					full_extensions(),
+				)?;
+				let gap_storage_changes = gap_changes
+					.drain_storage_changes(&state_ext.backend, &mut Default::default(), *spec_state_version)
+					.unwrap();
+				state_ext.backend.apply_transaction(
+					gap_storage_changes.transaction_storage_root,
+					gap_storage_changes.transaction,
+				);
+
+				last_executed = Some(missing);
+				missing += One::one();
+			}
+		}
+
 		let method = if command.checking {
 			"Core_execute_block"
 		} else {
@@ -189,15 +496,57 @@ where
 			storage_changes.transaction,
 		);
 
+		let new_root = *state_ext.as_backend().root();
 		log::info!(
 			target: LOG_TARGET,
 			"executed block {}, consumed weight {}, new storage root {:?}",
 			number,
 			consumed_weight,
-			state_ext.as_backend().root(),
+			new_root,
 		);
-	}
 
-	log::error!(target: LOG_TARGET, "ws subscription must have terminated.");
-	Ok(())
+		if command.verify_state_root && new_root != *block.header().state_root() {
+			log::error!(
+				target: LOG_TARGET,
+				"state root mismatch at block {}: computed {:?}, expected {:?}",
+				number,
+				new_root,
+				block.header().state_root(),
+			);
+
+			let touched_keys = storage_changes.main_storage_changes.iter().map(|(k, _)| k.clone());
+			let diffs =
+				diff_touched_keys::<Block>(&command.uri, hash, state_ext.as_backend(), touched_keys)
+					.await;
+			for diff in &diffs {
+				log::error!(
+					target: LOG_TARGET,
+					"diverging key {:?}: local {:?}, remote {:?}",
+					diff.key,
+					diff.local,
+					diff.remote,
+				);
+			}
+
+			if command.halt_on_mismatch {
+				return Err("state root mismatch while following chain".into())
+			}
+		}
+
+		last_executed = Some(*number);
+
+		if let Some(path) = &command.snapshot_path {
+			if let Err(e) = save_snapshot::<Block>(
+				path,
+				state_ext.as_backend(),
+				*number,
+				hash,
+				&config.chain_spec.name(),
+				*spec_version,
+				*spec_state_version,
+			) {
+				log::warn!(target: LOG_TARGET, "failed to write snapshot to {:?}: {:?}", path, e);
+			}
+		}
+	}
 }